@@ -104,31 +104,51 @@ use hash32_derive::Hash32;
 pub use signal_macros::Signal;
 
 /// A signal identifier
+///
+/// The inner value is the signal's global bit index (`word * 32 + bit`).
 #[derive(Clone, Copy, Debug, Eq, Hash32, PartialEq)]
-pub struct Id(u8);
+pub struct Id(u16);
 
-impl From<Id> for u8 {
-    fn from(id: Id) -> u8 {
+impl From<Id> for u16 {
+    fn from(id: Id) -> u16 {
         id.0
     }
 }
 
 /// A snapshot of the signals that *were* set
 #[derive(Clone, Copy, Debug)]
-pub struct Signals(usize);
+pub struct Signals {
+    words: [usize; W],
+    // index of the word the iterator is currently draining
+    word: usize,
+}
 
 impl Signals {
     /// Returns a snapshot of the signals currently set
     ///
     /// **NOTE**: this will clear all the currently set signals
     pub fn read() -> Self {
-        // NOTE(Ordering::Relaxed) we assume a single core target
-        Signals(SIGNALS.swap(0, Ordering::Relaxed))
+        let mut words = [0; W];
+        for (dst, signals) in words.iter_mut().zip(SIGNALS.iter()) {
+            // NOTE(Ordering::Relaxed) we assume a single core target
+            *dst = signals.swap(0, Ordering::Relaxed);
+        }
+        Signals { words, word: 0 }
     }
 
     /// Returns `true` if no signal is set in this snapshot
     pub fn is_empty(&self) -> bool {
-        self.0 == 0
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Returns `true` if `id` is set in this snapshot
+    ///
+    /// Unlike iterating, this does not consume the entry.
+    pub fn contains(&self, id: Id) -> bool {
+        let idx = usize::from(id.0);
+        let word = idx / 32;
+        let bit = idx % 32;
+        word < W && self.words[word] & (1 << bit) != 0
     }
 }
 
@@ -136,17 +156,28 @@ impl Iterator for Signals {
     type Item = Id;
 
     fn next(&mut self) -> Option<Id> {
-        if self.0 == 0 {
-            None
-        } else {
-            let pos = 31 - self.0.leading_zeros() as u8;
-            self.0 &= !(1 << pos);
-            Some(Id(pos))
+        // walk the words in order, draining each one bit at a time
+        while self.word < W {
+            let word = self.words[self.word];
+            if word == 0 {
+                self.word += 1;
+                continue;
+            }
+
+            let bit = 31 - word.leading_zeros() as usize;
+            self.words[self.word] &= !(1 << bit);
+            return Some(Id((self.word * 32 + bit) as u16));
         }
+
+        None
     }
 }
 
-static SIGNALS: AtomicUsize = AtomicUsize::new(0);
+/// Number of 32-bit words in the signal bitmap; total capacity is `32 * W`
+const W: usize = 2;
+
+// NOTE the number of elements must match `W`
+static SIGNALS: [AtomicUsize; W] = [AtomicUsize::new(0), AtomicUsize::new(0)];
 
 // Bit banding
 const RAM_START: usize = 0x2000_0000;
@@ -156,15 +187,20 @@ const ALIAS_START: usize = 0x2200_0000;
 pub unsafe trait Signal {
     /// The identifier for this signal
     fn id() -> Id {
-        Id(Self::usize() as u8)
+        Id(Self::usize() as u16)
     }
 
     /// IMPLEMENTATION DETAIL. DO NOT USE
     #[doc(hidden)]
     fn ptr() -> *const AtomicUsize {
-        let id = Self::usize();
-        let p = &SIGNALS as *const AtomicUsize as usize;
-        ((32 * p.wrapping_sub(RAM_START)).wrapping_add(ALIAS_START) + 4 * id) as *const AtomicUsize
+        // the linker-section offset maps onto `(word, bit)` of the bitmap
+        let offset = Self::usize();
+        let word = offset / 32;
+        let bit = offset % 32;
+
+        // bit-band alias of bit `bit` of `SIGNALS[word]`
+        let word_addr = &SIGNALS[word] as *const AtomicUsize as usize;
+        (ALIAS_START + 32 * word_addr.wrapping_sub(RAM_START) + 4 * bit) as *const AtomicUsize
     }
 
     /// Sets this signal
@@ -18,7 +18,7 @@ use core::{
 use cortex_m::peripheral::syst::SystClkSource;
 use cortex_m_rt::{entry, exception};
 use cortex_m_semihosting::hprintln;
-use executor::{Executor, Router};
+use executor::{Executor, Instant, Router, Task, TimerQueue};
 use pin_utils::pin_mut;
 use signal::Signal;
 
@@ -34,16 +34,17 @@ fn main() -> ! {
     syst.enable_interrupt();
 
     let router = &Router::new();
-    let executor = Executor::new(router);
+    let timer_queue = &TimerQueue::new();
+    let executor = Executor::new(router, timer_queue);
     pin_mut!(executor);
 
-    let t1 = T1::new(router);
+    let t1 = Task::new(T1::new(router));
     pin_mut!(t1);
-    executor.as_mut().spawn(t1).ok();
+    executor.as_mut().spawn(t1);
 
-    let t2 = T2::new(router);
+    let t2 = Task::new(T2::new(router));
     pin_mut!(t2);
-    executor.as_mut().spawn(t2).ok();
+    executor.as_mut().spawn(t2);
 
     executor.run()
 }
@@ -52,6 +53,9 @@ fn main() -> ! {
 fn SysTick() {
     static mut COUNT: u8 = 0;
 
+    // keep the monotonic counter used by `Timer` ticking
+    Instant::on_overflow();
+
     *COUNT += 1;
 
     // Send signal A every second
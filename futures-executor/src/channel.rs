@@ -0,0 +1,133 @@
+//! An async single-producer single-consumer channel
+//!
+//! This pairs a `heapless::spsc::Queue` with a dedicated [`Signal`] so that an
+//! interrupt handler can hand data to a task: the [`Sender`] enqueues an item
+//! and sets the signal, and the [`Receiver`] is a future that sleeps on that
+//! signal until an item is available. It hides the `router.route(..)`
+//! boilerplate the `T1`/`T2` example futures have to write by hand.
+
+use core::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{LocalWaker, Poll},
+};
+
+use heapless::{
+    spsc::{Consumer, Producer, Queue},
+    ArrayLength,
+};
+use signal::Signal;
+
+use crate::Router;
+
+/// The backing store of an async channel
+///
+/// Call [`split`](Channel::split) to obtain the [`Sender`] / [`Receiver`] pair.
+pub struct Channel<T, N, S>
+where
+    N: ArrayLength<T>,
+    S: Signal,
+{
+    queue: Queue<T, N>,
+    _signal: PhantomData<S>,
+}
+
+impl<T, N, S> Channel<T, N, S>
+where
+    N: ArrayLength<T>,
+    S: Signal,
+{
+    /// Creates an empty channel backed by signal `S`
+    #[inline]
+    pub fn new() -> Self {
+        Channel {
+            queue: Queue::new(),
+            _signal: PhantomData,
+        }
+    }
+
+    /// Splits the channel into a producer and a consumer half sharing the same
+    /// backing queue and signal id
+    #[inline]
+    pub fn split<'a>(
+        &'a mut self,
+        router: &'a Router,
+    ) -> (Sender<'a, T, N, S>, Receiver<'a, T, N, S>) {
+        let (producer, consumer) = self.queue.split();
+        (
+            Sender {
+                producer,
+                _signal: PhantomData,
+            },
+            Receiver {
+                consumer,
+                router,
+                _signal: PhantomData,
+            },
+        )
+    }
+}
+
+/// The producer half of a [`Channel`]
+///
+/// Usable from an interrupt handler.
+pub struct Sender<'a, T, N, S>
+where
+    N: ArrayLength<T>,
+    S: Signal,
+{
+    producer: Producer<'a, T, N>,
+    _signal: PhantomData<S>,
+}
+
+impl<'a, T, N, S> Sender<'a, T, N, S>
+where
+    N: ArrayLength<T>,
+    S: Signal,
+{
+    /// Enqueues `item` and wakes the receiving task
+    ///
+    /// Returns the `item` back if the queue is full.
+    #[inline]
+    pub fn send(&mut self, item: T) -> Result<(), T> {
+        self.producer.enqueue(item)?;
+        S::set();
+        Ok(())
+    }
+}
+
+/// The consumer half of a [`Channel`]
+///
+/// Awaiting a `Receiver` yields the next item, sleeping on signal `S` while the
+/// queue is empty.
+pub struct Receiver<'a, T, N, S>
+where
+    N: ArrayLength<T>,
+    S: Signal,
+{
+    consumer: Consumer<'a, T, N>,
+    router: &'a Router,
+    _signal: PhantomData<S>,
+}
+
+impl<'a, T, N, S> Future for Receiver<'a, T, N, S>
+where
+    N: ArrayLength<T>,
+    S: Signal,
+{
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<T> {
+        // `Receiver` holds no pinned data
+        let this = self.get_mut();
+
+        if let Some(item) = this.consumer.dequeue() {
+            Poll::Ready(item)
+        } else {
+            // go to sleep until the sender sets `S`
+            this.router.route(S::id(), lw.clone());
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! A preemptive executor bound to an NVIC interrupt line
+//!
+//! Several `InterruptExecutor`s can run at different Cortex-M interrupt
+//! priorities, in the spirit of embassy's interrupt-executors. Because the NVIC
+//! preempts a lower-priority handler when a higher-priority interrupt is
+//! pended, a task on a high-priority executor preempts a task on a low-priority
+//! one; the `wfe`-based [`Executor`](crate::Executor) remains the lowest,
+//! thread-mode, priority.
+//!
+//! **IMPORTANT**: data shared between tasks running at different priorities must
+//! be protected with the bit-band [`Signal`](signal::Signal)s or a critical
+//! section. `Signals::read`'s `Ordering::Relaxed` is only sound because we
+//! assume a single core.
+
+use core::{cell::Cell, future::Future, pin::Pin, ptr::NonNull};
+
+use cortex_m::interrupt::Nr;
+
+use crate::task::{self, SchedContext, Task};
+
+/// An executor whose run queue is drained from an interrupt handler
+///
+/// `spawn` and every waker pend the associated NVIC interrupt instead of
+/// enqueueing into a polled loop; the handler body calls [`on_interrupt`] to
+/// drain the ready queue.
+///
+/// [`on_interrupt`]: InterruptExecutor::on_interrupt
+pub struct InterruptExecutor {
+    ctx: SchedContext,
+    next_id: Cell<u8>,
+}
+
+impl InterruptExecutor {
+    /// Creates an executor bound to the `irq` interrupt line
+    ///
+    /// The caller is responsible for setting `irq`'s priority and enabling it in
+    /// the NVIC; the line must not be used for anything else.
+    #[inline]
+    pub fn new<I>(irq: I) -> Self
+    where
+        I: Nr,
+    {
+        InterruptExecutor {
+            ctx: SchedContext::new(Some(irq.nr())),
+            next_id: Cell::new(0),
+        }
+    }
+
+    /// Spawns the given `task` node and pends the executor's interrupt
+    #[inline]
+    pub fn spawn<'a, F>(self: Pin<&'a Self>, task: Pin<&'a mut Task<F>>)
+    where
+        F: Future<Output = !> + 'a,
+    {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        crate::trace::task_new(id);
+
+        // NOTE(NonNull) OK because `self` is pinned thus `ctx` is also immovable
+        let ctx = NonNull::from(&self.ctx);
+        unsafe {
+            // `link` enqueues the task, which pends the interrupt for us
+            task::link(task, ctx, id);
+        }
+    }
+
+    /// Drains the ready queue
+    ///
+    /// Call this from the body of the executor's interrupt handler.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        while let Some(header) = self.ctx.dequeue() {
+            unsafe {
+                let id = header.as_ref().id();
+                crate::trace::task_exec_begin(id);
+                task::poll(header);
+                crate::trace::task_exec_end(id);
+            }
+        }
+    }
+}
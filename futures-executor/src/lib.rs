@@ -5,19 +5,38 @@
 #![no_std]
 
 use core::{
-    cell::{RefCell, UnsafeCell},
+    cell::{Cell, RefCell},
     future::Future,
-    hint,
     pin::Pin,
     ptr::NonNull,
-    task::{LocalWaker, UnsafeWake, Waker},
+    task::LocalWaker,
 };
 
 use cortex_m::asm;
-use heapless::{consts, spsc::Queue, FnvIndexMap, Vec};
-use signal::{Id, Signals};
-
-// For simplicity we hardcode the capacity of the executor
+use heapless::{consts, FnvIndexMap};
+use signal::{Id, Signal, Signals};
+
+pub use channel::{Channel, Receiver, Sender};
+pub use interrupt::InterruptExecutor;
+pub use task::Task;
+pub use timer::{Instant, Timer, TimerQueue};
+pub use trace::Tracer;
+pub use wait::{wait, SignalFuture};
+
+#[cfg(feature = "trace")]
+pub use trace::set_tracer;
+
+use task::SchedContext;
+
+mod channel;
+mod interrupt;
+mod task;
+mod timer;
+mod trace;
+mod wait;
+
+// The `Router` and `TimerQueue` are bounded by the number of *concurrent*
+// waits, not by the number of tasks, so a fixed capacity is still fine here
 //
 // The capacity could be generic but the trait bounds are annoying to write
 type SIZE = consts::U8;
@@ -25,6 +44,8 @@ type SIZE = consts::U8;
 /// Routes signals to tasks
 pub struct Router {
     wakers: RefCell<FnvIndexMap<Id, LocalWaker, SIZE>>,
+    // the most recent snapshot handled by the executor; read by `wait`
+    last: Cell<Option<Signals>>,
 }
 
 impl Router {
@@ -32,6 +53,7 @@ impl Router {
     pub fn new() -> Self {
         Router {
             wakers: RefCell::new(FnvIndexMap::new()),
+            last: Cell::new(None),
         }
     }
 
@@ -43,121 +65,125 @@ impl Router {
         self.wakers.borrow_mut().insert(signal, waker).ok();
     }
 
+    /// Suspends the current task until signal `S` fires
+    ///
+    /// The ergonomic form of `route`: `wait::<S>(router).await` instead of a
+    /// hand-written `Future`.
     #[inline]
-    fn wake(&self, signal: Id) {
-        if let Some(waker) = self.wakers.borrow_mut().remove(&signal) {
-            waker.wake();
-        }
+    pub fn wait<S>(&self) -> SignalFuture<S>
+    where
+        S: Signal,
+    {
+        wait(self)
     }
-}
-
-struct Task {
-    id: u8,
-    // XXX kind of wasteful because all `Task`s will have the same pointer
-    ready_queue: NonNull<UnsafeCell<Queue<u8, SIZE>>>,
-}
 
-// HACK Task is NOT Send or Sync but the UnsafeWake trait requires these so ...
-unsafe impl Send for Task {}
-unsafe impl Sync for Task {}
-
-unsafe impl UnsafeWake for Task {
+    /// Returns `true` if `signal` was set in the most recent snapshot
     #[inline]
-    unsafe fn clone_raw(&self) -> Waker {
-        Waker::new(NonNull::from(self as &UnsafeWake))
+    pub fn fired(&self, signal: Id) -> bool {
+        self.last.get().map_or(false, |s| s.contains(signal))
     }
 
+    // Records the snapshot the executor is about to dispatch
     #[inline]
-    unsafe fn drop_raw(&self) {}
-
-    #[inline]
-    unsafe fn wake(&self) {
-        unreachable!()
+    fn record(&self, signals: Signals) {
+        self.last.set(Some(signals));
     }
 
     #[inline]
-    unsafe fn wake_local(&self) {
-        (*self.ready_queue.as_ref().get()).enqueue_unchecked(self.id)
+    fn wake(&self, signal: Id) {
+        if let Some(waker) = self.wakers.borrow_mut().remove(&signal) {
+            waker.wake();
+        }
     }
 }
 
 pub struct Executor<'a> {
-    // all futures
-    futures: Vec<Pin<&'a mut Future<Output = !>>, SIZE>,
-    // all "tasks"
-    // XXX we only need this because LocalWakers need to point to something ...
-    tasks: Vec<Task, SIZE>,
-    // queue of ready tasks (IDs only)
-    ready_queue: UnsafeCell<Queue<u8, SIZE>>,
+    // intrusive list of ready tasks, threaded through the task nodes themselves
+    ctx: SchedContext,
+    // next task identifier to hand out
+    next_id: Cell<u8>,
     router: &'a Router,
+    timer_queue: &'a TimerQueue,
 }
 
 impl<'a> Executor<'a> {
     #[inline]
-    pub fn new(router: &'a Router) -> Self {
+    pub fn new(router: &'a Router, timer_queue: &'a TimerQueue) -> Self {
         Executor {
-            futures: Vec::new(),
-            tasks: Vec::new(),
-            ready_queue: UnsafeCell::new(Queue::new()),
+            // the thread-mode executor is released by `wfe`, so it has no IRQ
+            ctx: SchedContext::new(None),
+            next_id: Cell::new(0),
             router,
+            timer_queue,
         }
     }
 
-    /// Spawns the given `fut`-ure as a task
+    /// Spawns the given `task` node
     ///
-    /// Note that the task won't start or make progress until `run` is called
+    /// The caller owns the statically allocated, pinned [`Task`]; the executor
+    /// just links it into its run queue. The task won't start or make progress
+    /// until `run` is called.
     #[inline]
-    pub fn spawn(mut self: Pin<&mut Self>, fut: Pin<&'a mut Future<Output = !>>) -> Result<(), ()> {
-        let id = self.tasks.len() as u8;
-
-        self.futures.push(fut).map_err(drop)?;
+    pub fn spawn<F>(self: Pin<&mut Self>, task: Pin<&'a mut Task<F>>)
+    where
+        F: Future<Output = !> + 'a,
+    {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
 
-        // NOTE(NonNull) OK because `self` is pinned thus `ready_queue` is also immovable
-        let nn = NonNull::from(&self.ready_queue);
-        self.tasks
-            .push(Task {
-                id,
-                ready_queue: nn,
-            })
-            .unwrap_or_else(|_| unsafe { hint::unreachable_unchecked() });
+        trace::task_new(id);
 
+        // NOTE(NonNull) OK because `self` is pinned thus `ctx` is also immovable
+        let ctx = NonNull::from(&self.ctx);
         unsafe {
-            (*self.ready_queue.get()).enqueue_unchecked(id);
+            task::link(task, ctx, id);
         }
-
-        Ok(())
     }
 
     /// Runs all the spawned tasks
     #[inline]
-    pub fn run(mut self: Pin<&mut Self>) -> ! {
+    pub fn run(self: Pin<&mut Self>) -> ! {
         loop {
             unsafe {
                 // advance ready tasks
-                while let Some(id) = (*self.ready_queue.get()).dequeue() {
-                    let task = self.tasks.as_ptr().add(usize::from(id)) as *mut Task;
-
-                    // NOTE(NonNull) OK because `self` is pinned thus `tasks` is also immovable
-                    let lw = LocalWaker::new(NonNull::new_unchecked(task));
-
-                    self.futures
-                        .get_unchecked_mut(usize::from(id))
-                        .as_mut()
-                        .poll(&lw);
+                while let Some(header) = self.ctx.dequeue() {
+                    let id = header.as_ref().id();
+                    trace::task_exec_begin(id);
+                    task::poll(header);
+                    trace::task_exec_end(id);
                 }
 
-                // wait for a signal
-                let mut signals;
-                loop {
-                    signals = Signals::read();
+                // wait for a signal or a timer to expire
+                let signals = loop {
+                    // wake any task whose deadline has passed
+                    let expired = self.timer_queue.wake_expired(Instant::now());
 
-                    if !signals.is_empty() {
-                        break;
+                    let signals = Signals::read();
+                    if expired || !signals.is_empty() {
+                        break signals;
                     }
 
+                    // `timer::arm` programs no interrupt: `wfe` is released by
+                    // the periodic `SysTick`, up to one period after the
+                    // earliest pending deadline
+                    if let Some(deadline) = self.timer_queue.next_deadline() {
+                        timer::arm(deadline);
+
+                        // re-read `now` *after* arming: if the deadline slipped
+                        // past in the meantime, loop again instead of sleeping
+                        // until the counter wraps around
+                        if Instant::now().ticks() >= deadline {
+                            continue;
+                        }
+                    }
+
+                    trace::system_idle();
                     asm::wfe();
-                }
+                };
 
+                // publish the snapshot so `wait` futures can confirm their
+                // signal fired, then wake the routed tasks
+                self.router.record(signals);
                 for id in signals {
                     self.router.wake(id);
                 }
@@ -0,0 +1,256 @@
+//! Intrusive task nodes and the run queue threaded through them
+//!
+//! Each spawned task is a statically allocated [`Task`] node whose first field
+//! is a [`TaskHeader`]. The header embeds the run-queue link, so the ready queue
+//! is an intrusive singly linked list rather than a fixed-capacity
+//! `heapless::Queue`, and the `LocalWaker` can point straight at the header
+//! (recovered back into the concrete `Task<F>` by [`poll_task`]) instead of at
+//! an entry of a separate `tasks` vector.
+
+use core::{
+    cell::{Cell, UnsafeCell},
+    future::Future,
+    pin::Pin,
+    ptr::NonNull,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{LocalWaker, UnsafeWake, Waker},
+};
+
+use cortex_m::{interrupt, peripheral::NVIC};
+
+// `state` bit: the task is linked into a run queue
+const RUN_QUEUED: u32 = 1 << 0;
+
+/// The scheduler bookkeeping shared by every task
+pub struct TaskHeader {
+    // a stable, per-executor identifier handed out by `spawn`
+    id: Cell<u8>,
+    // RUN_QUEUED and room for future flags
+    state: AtomicU32,
+    // next node in the intrusive run queue
+    next: Cell<Option<NonNull<TaskHeader>>>,
+    // the scheduling context this task is spawned on; set by `spawn`
+    ctx: Cell<Option<NonNull<SchedContext>>>,
+    // monomorphized routine that polls the `Task<F>` this header belongs to
+    poll_fn: Cell<Option<unsafe fn(NonNull<TaskHeader>, &LocalWaker)>>,
+}
+
+// HACK like the old `Task`, a header is NOT Send/Sync but `UnsafeWake` requires
+// these; sound because we only ever touch it on a single core
+unsafe impl Send for TaskHeader {}
+unsafe impl Sync for TaskHeader {}
+
+impl TaskHeader {
+    const fn new() -> Self {
+        TaskHeader {
+            id: Cell::new(0),
+            state: AtomicU32::new(0),
+            next: Cell::new(None),
+            ctx: Cell::new(None),
+            poll_fn: Cell::new(None),
+        }
+    }
+
+    /// The stable identifier assigned to this task by the executor
+    #[inline]
+    pub fn id(&self) -> u8 {
+        self.id.get()
+    }
+}
+
+unsafe impl UnsafeWake for TaskHeader {
+    #[inline]
+    unsafe fn clone_raw(&self) -> Waker {
+        Waker::new(NonNull::from(self as &UnsafeWake))
+    }
+
+    #[inline]
+    unsafe fn drop_raw(&self) {}
+
+    #[inline]
+    unsafe fn wake(&self) {
+        self.wake_local()
+    }
+
+    #[inline]
+    unsafe fn wake_local(&self) {
+        // mark the task ready; only link it once per pending wake
+        if self.state.fetch_or(RUN_QUEUED, Ordering::Relaxed) & RUN_QUEUED == 0 {
+            crate::trace::task_ready_begin(self.id.get());
+            if let Some(ctx) = self.ctx.get() {
+                ctx.as_ref().enqueue(NonNull::from(self));
+            }
+        }
+    }
+}
+
+/// An intrusive LIFO run queue threaded through [`TaskHeader::next`]
+///
+/// With the `InterruptExecutor` a task can be woken from a handler that
+/// preempts the one draining the queue, so the head is guarded by a short
+/// critical section rather than relying on thread-mode-only access.
+struct RunQueue {
+    head: Cell<Option<NonNull<TaskHeader>>>,
+}
+
+impl RunQueue {
+    const fn new() -> Self {
+        RunQueue {
+            head: Cell::new(None),
+        }
+    }
+
+    #[inline]
+    unsafe fn enqueue(&self, task: NonNull<TaskHeader>) {
+        interrupt::free(|_| {
+            task.as_ref().next.set(self.head.get());
+            self.head.set(Some(task));
+        })
+    }
+
+    #[inline]
+    fn dequeue(&self) -> Option<NonNull<TaskHeader>> {
+        interrupt::free(|_| {
+            let head = self.head.get()?;
+            unsafe {
+                self.head.set(head.as_ref().next.get());
+                head.as_ref().next.set(None);
+                // clear RUN_QUEUED so a wake during `poll` re-links the task
+                head.as_ref().state.fetch_and(!RUN_QUEUED, Ordering::Relaxed);
+            }
+            Some(head)
+        })
+    }
+}
+
+/// The scheduling state shared between a task and the executor running it
+///
+/// Both the thread-mode `Executor` and the `InterruptExecutor` embed one. The
+/// `irq` is the NVIC line to `pend` when a task is woken: `None` for the
+/// thread-mode executor (whose `run` loop is released by `wfe`), `Some` for an
+/// interrupt executor.
+pub struct SchedContext {
+    run_queue: RunQueue,
+    irq: Cell<Option<u8>>,
+}
+
+impl SchedContext {
+    /// Creates a scheduling context that pends `irq` on wake, if any
+    #[inline]
+    pub const fn new(irq: Option<u8>) -> Self {
+        SchedContext {
+            run_queue: RunQueue::new(),
+            irq: Cell::new(irq),
+        }
+    }
+
+    /// Pops the next ready task, if any
+    #[inline]
+    pub fn dequeue(&self) -> Option<NonNull<TaskHeader>> {
+        self.run_queue.dequeue()
+    }
+
+    unsafe fn enqueue(&self, task: NonNull<TaskHeader>) {
+        self.run_queue.enqueue(task);
+        if let Some(nr) = self.irq.get() {
+            // release the executor's interrupt; the NVIC will preempt any
+            // lower-priority handler that is currently running
+            pend(nr);
+        }
+    }
+}
+
+/// Pends an NVIC interrupt by its raw number
+#[inline]
+unsafe fn pend(nr: u8) {
+    let word = usize::from(nr / 32);
+    let bit = nr % 32;
+    (*NVIC::ptr()).ispr[word].write(1 << bit);
+}
+
+/// A statically allocated task: a [`TaskHeader`] followed by its future
+///
+/// The `#[repr(C)]` layout guarantees the header is at offset zero, so a
+/// `NonNull<TaskHeader>` can be cast back to a `*const Task<F>`.
+#[repr(C)]
+pub struct Task<F>
+where
+    F: Future<Output = !>,
+{
+    header: TaskHeader,
+    future: UnsafeCell<F>,
+}
+
+impl<F> Task<F>
+where
+    F: Future<Output = !>,
+{
+    /// Wraps `future` into a spawnable task node
+    #[inline]
+    pub fn new(future: F) -> Self {
+        Task {
+            header: TaskHeader::new(),
+            future: UnsafeCell::new(future),
+        }
+    }
+}
+
+/// Polls the `Task<F>` that `header` belongs to
+///
+/// # Safety
+///
+/// `header` must point at the header of a live, pinned `Task<F>`.
+unsafe fn poll_task<F>(header: NonNull<TaskHeader>, lw: &LocalWaker)
+where
+    F: Future<Output = !>,
+{
+    let task = task_from_header::<F>(header);
+    let future = Pin::new_unchecked(&mut *task.future.get());
+    let _ = future.poll(lw);
+}
+
+/// Recovers the `Task<F>` a header is embedded in
+#[inline]
+unsafe fn task_from_header<'a, F>(header: NonNull<TaskHeader>) -> &'a Task<F>
+where
+    F: Future<Output = !>,
+{
+    &*(header.as_ptr() as *const Task<F>)
+}
+
+/// Links a pinned task node onto `ctx`, returning its header
+///
+/// # Safety
+///
+/// `ctx` must outlive the task (`ctx` is owned by a pinned executor, the task
+/// node by the caller).
+pub(crate) unsafe fn link<'a, F>(
+    task: Pin<&'a mut Task<F>>,
+    ctx: NonNull<SchedContext>,
+    id: u8,
+) -> NonNull<TaskHeader>
+where
+    F: Future<Output = !> + 'a,
+{
+    let task = task.get_unchecked_mut();
+    let header = &task.header;
+    header.id.set(id);
+    header.ctx.set(Some(ctx));
+    header.poll_fn.set(Some(poll_task::<F>));
+    header.state.store(RUN_QUEUED, Ordering::Relaxed);
+
+    let header = NonNull::from(header);
+    ctx.as_ref().enqueue(header);
+    header
+}
+
+/// Polls the task behind `header`, building the `LocalWaker` that points at it
+///
+/// # Safety
+///
+/// `header` must come out of a run queue populated by [`link`].
+pub(crate) unsafe fn poll(header: NonNull<TaskHeader>) {
+    let lw = LocalWaker::new(NonNull::from(header.as_ref() as &UnsafeWake));
+    let poll_fn = header.as_ref().poll_fn.get().unwrap();
+    poll_fn(header, &lw);
+}
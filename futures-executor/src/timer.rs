@@ -0,0 +1,176 @@
+//! A monotonic timer and an integrated timer queue
+//!
+//! This lets a task `.await` the passage of time instead of only reacting to
+//! hardware `Signal`s. It is modeled after embassy-time's integrated timer
+//! queue: the [`TimerQueue`] keeps a list of pending deadlines sorted by how
+//! soon they expire and the [`Executor`](crate::Executor) wakes the tasks whose
+//! deadlines have passed. The hardware timer is not programmed per deadline:
+//! `wfe` is released by the periodic `SysTick` exception, so a task is woken up
+//! to one `SysTick` period after its deadline.
+
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicU32, Ordering},
+    task::{LocalWaker, Poll},
+};
+
+use cortex_m::peripheral::SYST;
+use heapless::Vec;
+
+use crate::SIZE;
+
+// The `SysTick` reload value, i.e. how many ticks the free-running counter
+// covers between two `SysTick` exceptions.
+//
+// This *must* match the reload the application programs into `RVR` (see
+// `futures-app`); `SysTick` is a 24-bit down-counter and we never reprogram
+// `RVR` ourselves, so `Instant` and the app's periodic cadence stay consistent.
+const RELOAD: u32 = 12_000_000;
+
+// Number of times the free-running counter has wrapped around
+//
+// Bumped by the `SysTick` handler; see [`Instant::on_overflow`].
+static OVERFLOWS: AtomicU32 = AtomicU32::new(0);
+
+/// A measurement of the monotonic counter, in timer ticks
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Instant(u64);
+
+impl Instant {
+    /// Reads the current value of the monotonic counter
+    pub fn now() -> Self {
+        // `SysTick` counts *down* from `RELOAD` to `0`; the high bits live in
+        // `OVERFLOWS`. We re-read the overflow count to make sure it didn't
+        // change while we were reading the low 24 bits.
+        loop {
+            let hi = OVERFLOWS.load(Ordering::Relaxed);
+            let low = RELOAD - SYST::get_current();
+            if hi == OVERFLOWS.load(Ordering::Relaxed) {
+                // each wrap-around covers exactly `RELOAD` ticks
+                break Instant(u64::from(hi) * u64::from(RELOAD) + u64::from(low));
+            }
+        }
+    }
+
+    /// The raw tick count
+    pub fn ticks(self) -> u64 {
+        self.0
+    }
+
+    /// Records that the free-running counter wrapped around
+    ///
+    /// Must be called from the `SysTick` handler
+    pub fn on_overflow() {
+        OVERFLOWS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A sorted list of pending `(deadline, waker)` entries
+///
+/// The executor drains the expired entries on every iteration of its `run`
+/// loop; no hardware timer is armed for the earliest remaining one (see `arm`).
+pub struct TimerQueue {
+    // kept sorted by deadline in *descending* order so that the earliest
+    // deadline sits at the back and can be popped without shifting the rest
+    entries: RefCell<Vec<(u64, LocalWaker), SIZE>>,
+}
+
+impl TimerQueue {
+    /// Creates an empty timer queue
+    #[inline]
+    pub fn new() -> Self {
+        TimerQueue {
+            entries: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Registers `waker` to be woken once `deadline` has passed
+    ///
+    /// Panics if the queue is full: silently dropping the waker would leave the
+    /// `Timer` future `Pending` forever, hanging the task with no diagnostic.
+    fn insert(&self, deadline: u64, waker: LocalWaker) {
+        let mut entries = self.entries.borrow_mut();
+        if entries.push((deadline, waker)).is_err() {
+            panic!("TimerQueue is full");
+        }
+
+        // bubble the new entry towards the front until the list is sorted by
+        // descending deadline again
+        let mut i = entries.len() - 1;
+        while i > 0 && entries[i].0 > entries[i - 1].0 {
+            entries.swap(i, i - 1);
+            i -= 1;
+        }
+    }
+
+    /// Wakes every task whose deadline is at or before `now`
+    ///
+    /// Returns `true` if at least one task was woken
+    pub(crate) fn wake_expired(&self, now: Instant) -> bool {
+        let mut entries = self.entries.borrow_mut();
+        let mut woke = false;
+        // the earliest deadline is at the back of the list
+        while entries.last().map(|&(d, _)| d <= now.0).unwrap_or(false) {
+            let (_, waker) = entries.pop().unwrap();
+            waker.wake();
+            woke = true;
+        }
+        woke
+    }
+
+    /// The earliest pending deadline, if any
+    pub(crate) fn next_deadline(&self) -> Option<u64> {
+        self.entries.borrow().last().map(|&(d, _)| d)
+    }
+}
+
+/// A future that resolves once a deadline has passed
+pub struct Timer<'a> {
+    queue: &'a TimerQueue,
+    deadline: u64,
+    armed: bool,
+}
+
+impl<'a> Timer<'a> {
+    /// Returns a future that resolves `ticks` ticks from now
+    pub fn after(queue: &'a TimerQueue, ticks: u64) -> Self {
+        Timer {
+            queue,
+            deadline: Instant::now().0 + ticks,
+            armed: false,
+        }
+    }
+}
+
+impl<'a> Future for Timer<'a> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<()> {
+        // `Timer` holds no pinned data
+        let this = self.get_mut();
+
+        if Instant::now().0 >= this.deadline {
+            Poll::Ready(())
+        } else {
+            // register the waker the first time we're polled; later polls only
+            // re-check the deadline
+            if !this.armed {
+                this.queue.insert(this.deadline, lw.clone());
+                this.armed = true;
+            }
+            Poll::Pending
+        }
+    }
+}
+
+/// Does nothing: no interrupt is scheduled for `deadline` itself
+///
+/// `SysTick` has no compare register and its reload doubles as the monotonic
+/// counter's period, so `RVR` is not reprogrammed: doing so would corrupt
+/// `Instant` accounting and the app's periodic cadence. The executor relies on
+/// the periodic `SysTick` exception instead, which releases `wfe` once per
+/// `RELOAD` ticks; `wake_expired` then wakes the task once its deadline has
+/// passed. A task is thus woken up to one `SysTick` period after its deadline.
+pub(crate) fn arm(_deadline: u64) {}
@@ -0,0 +1,71 @@
+//! Optional scheduler tracing hooks
+//!
+//! When the `trace` feature is enabled the executor emits task-id events around
+//! spawning, polling and waking tasks, plus a system-idle event before it goes
+//! to sleep. A backend such as `rtos-trace`/SystemView registers a [`Tracer`]
+//! with [`set_tracer`] and reconstructs a scheduling timeline from them. With
+//! the feature disabled every hook compiles away, so there is no runtime cost.
+
+/// A sink for scheduler trace events
+///
+/// Each task's stable `u8` id maps directly onto a trace task handle.
+pub trait Tracer: Sync {
+    /// A task was spawned
+    fn task_new(&self, id: u8);
+    /// The executor started polling a task
+    fn task_exec_begin(&self, id: u8);
+    /// The executor finished polling a task
+    fn task_exec_end(&self, id: u8);
+    /// A task was made ready (woken)
+    fn task_ready_begin(&self, id: u8);
+    /// The executor is about to sleep
+    fn system_idle(&self);
+}
+
+#[cfg(feature = "trace")]
+static mut TRACER: Option<&'static dyn Tracer> = None;
+
+/// Registers the global trace backend
+///
+/// Call this once, before spawning any task.
+#[cfg(feature = "trace")]
+pub fn set_tracer(tracer: &'static dyn Tracer) {
+    unsafe {
+        TRACER = Some(tracer);
+    }
+}
+
+#[cfg(feature = "trace")]
+#[inline(always)]
+fn with<F>(f: F)
+where
+    F: FnOnce(&'static dyn Tracer),
+{
+    if let Some(tracer) = unsafe { TRACER } {
+        f(tracer);
+    }
+}
+
+macro_rules! hook {
+    ($(#[$attr:meta])* $name:ident $(, $arg:ident: $ty:ty)*) => {
+        $(#[$attr])*
+        #[inline(always)]
+        pub(crate) fn $name($($arg: $ty),*) {
+            #[cfg(feature = "trace")]
+            with(|tracer| tracer.$name($($arg),*));
+            #[cfg(not(feature = "trace"))]
+            { $(let _ = $arg;)* }
+        }
+    };
+}
+
+hook!(/// A task was spawned
+    task_new, id: u8);
+hook!(/// The executor started polling a task
+    task_exec_begin, id: u8);
+hook!(/// The executor finished polling a task
+    task_exec_end, id: u8);
+hook!(/// A task was made ready (woken)
+    task_ready_begin, id: u8);
+hook!(/// The executor is about to sleep
+    system_idle);
@@ -0,0 +1,60 @@
+//! A first-class `wait(signal)` future
+//!
+//! This replaces the `route(X::id(), lw.clone())` + `return Pending` dance that
+//! the example futures repeat by hand. Instead of writing a `Future` by hand a
+//! task can `wait::<A>(router).await` inside an ordinary `async` block.
+
+use core::{future::Future, marker::PhantomData, pin::Pin, task::{LocalWaker, Poll}};
+
+use signal::Signal;
+
+use crate::Router;
+
+/// A future that resolves once signal `S` has fired at least once after the
+/// await point
+///
+/// Obtained from [`wait`] or [`Router::wait`](crate::Router::wait).
+pub struct SignalFuture<'a, S>
+where
+    S: Signal,
+{
+    router: &'a Router,
+    // whether the waker has been registered with the router yet
+    registered: bool,
+    _signal: PhantomData<S>,
+}
+
+impl<'a, S> Future for SignalFuture<'a, S>
+where
+    S: Signal,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, lw: &LocalWaker) -> Poll<()> {
+        // `SignalFuture` holds no pinned data
+        let this = self.get_mut();
+
+        // we're done once we've been woken *and* the signal shows up in the
+        // last snapshot; a bare wake without the signal set is spurious
+        if this.registered && this.router.fired(S::id()) {
+            Poll::Ready(())
+        } else {
+            this.router.route(S::id(), lw.clone());
+            this.registered = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// Suspends the current task until signal `S` fires
+#[inline]
+pub fn wait<S>(router: &Router) -> SignalFuture<S>
+where
+    S: Signal,
+{
+    SignalFuture {
+        router,
+        registered: false,
+        _signal: PhantomData,
+    }
+}